@@ -0,0 +1,7 @@
+#![feature(generators)]
+
+// Bloques reutilizables para armar modelos sobre el simulador y los modelos de ejemplo
+// construidos con ellos. main.rs los ejecuta desde la linea de comandos.
+#[macro_use]
+pub mod state;
+pub mod models;
@@ -1,6 +1,6 @@
 #![feature(generators)] 
 
-use std::{rc::Rc, cell::Cell, time::Duration};
+use std::{rc::Rc, cell::Cell, fmt::Display, str::FromStr, time::Duration};
 
 // Estructuras necesarias para modelar y ejecutar la simulación.
 // Simulation:  Estructura principal, encargada de ejecutar la simulación
@@ -11,7 +11,51 @@ use std::{rc::Rc, cell::Cell, time::Duration};
 // En palabras sencillas es un puntero inteligente que guarda un generador      
 use simulator::{Key, Simulation, GenBoxed, Action, State, StateKey};
 
+use example_sim::models;
+
 fn main() {
+    // `cargo run -- mm1 [tasa_llegada] [tasa_servicio] [confianza] [calentamiento_segundos] [paciencia_segundos]` y
+    // `cargo run -- mmc [tasa_llegada] [tasa_servicio] [servidores] [rr|least] [confianza] [calentamiento_segundos] [paciencia_segundos]`
+    // ejecutan los modelos de colas de ejemplo en lugar de las dos entidades de abajo.
+    // El M/M/1 es el M/M/c con un servidor. La semilla se toma de la variable de entorno SEED:
+    // un numero o `random` para elegir una nueva, que el reporte muestra para repetir la corrida.
+    let mut args = std::env::args().skip(1);
+    let model = args.next();
+    if let Some(model @ ("mm1" | "mmc")) = model.as_deref() {
+        let single = model == "mm1";
+        let arrival_rate = next_arg(&mut args, if single { 0.8 } else { 1.6 }, "tasa de llegada");
+        let service_rate = next_arg(&mut args, 1.0, "tasa de servicio");
+        let (servers, assignment) = if single {
+            (1, models::pool::Assignment::RoundRobin)
+        } else {
+            (
+                next_arg(&mut args, 2, "numero de servidores"),
+                next_arg(&mut args, models::pool::Assignment::RoundRobin, "politica de asignacion"),
+            )
+        };
+        let confidence = next_arg(&mut args, models::stats::ConfidenceLevel::P95, "nivel de confianza");
+        let warmup = args.next().map_or(Duration::ZERO, |arg| parse_seconds(&arg, "calentamiento"));
+        let patience = args.next().map(|arg| parse_seconds(&arg, "paciencia"));
+        let seed = match std::env::var("SEED").ok().as_deref() {
+            None => 42,
+            Some("random") => models::rng::Rng::entropy_seed(),
+            Some(arg) => parse_arg(arg, "semilla"),
+        };
+        let report = models::mmc::run(&models::mmc::Params {
+            arrival_rate,
+            service_rate,
+            servers,
+            assignment,
+            horizon: Duration::from_secs(10_000),
+            seed,
+            warmup,
+            confidence,
+            patience,
+        });
+        print!("{}", report);
+        return;
+    }
+
     // Instanciar el simulador
     let mut simulation = Simulation::default();
     
@@ -47,6 +91,31 @@ fn main() {
     simulation.run_with_limit(Duration::from_secs(60));
 }
 
+// Interpreta un argumento de la linea de comandos, terminando con un mensaje claro si no es valido
+fn parse_arg<T: FromStr>(arg: &str, name: &str) -> T
+where
+    T::Err: Display,
+{
+    arg.parse().unwrap_or_else(|e| panic!("valor invalido para {} ({}): {}", name, arg, e))
+}
+
+// Interpreta un tiempo en segundos, que debe ser un numero finito y no negativo
+fn parse_seconds(arg: &str, name: &str) -> Duration {
+    let seconds: f64 = parse_arg(arg, name);
+    if !(seconds.is_finite() && seconds >= 0.0 && seconds <= u64::MAX as f64) {
+        panic!("valor invalido para {} ({}): debe ser una cantidad de segundos no negativa", name, arg);
+    }
+    Duration::from_secs_f64(seconds)
+}
+
+// Lee el siguiente argumento o usa `default` si no hay mas
+fn next_arg<T: FromStr>(args: &mut impl Iterator<Item = String>, default: T, name: &str) -> T
+where
+    T::Err: Display,
+{
+    args.next().map_or(default, |arg| parse_arg(&arg, name))
+}
+
 fn entity_a(shared_state: Rc<Cell<State>>, entity_b_key: StateKey<Option<Key>>, entity_states_key: StateKey<Passivated>) -> GenBoxed<()> {
     Box::new(move |_|{
         // Extrae el state actual dejando uno nuevo en su lugar (temporalmente)
@@ -0,0 +1,351 @@
+use std::{fmt, rc::Rc, time::Duration};
+
+use simulator::{Simulation, StateKey};
+
+use super::{
+    pool::{Assignment, ServerPool},
+    queue::{poisson_arrivals, server, warmup, Station},
+    rng::Rng,
+    stats::ConfidenceLevel,
+};
+
+// Parametros del modelo M/M/c. Las tasas estan en clientes por segundo,
+// `service_rate` es la de cada servidor.
+#[derive(Clone, Copy)]
+pub struct Params {
+    pub arrival_rate: f64,
+    pub service_rate: f64,
+    pub servers: usize,
+    pub assignment: Assignment,
+    pub horizon: Duration,
+    pub seed: u64,
+    // Tiempo inicial descartado de las estadisticas, Duration::ZERO si no hay calentamiento
+    pub warmup: Duration,
+    // Nivel del intervalo de confianza reportado para la espera en cola
+    pub confidence: ConfidenceLevel,
+    // Espera maxima antes de que un cliente abandone la cola, None si nunca abandona
+    pub patience: Option<Duration>,
+}
+
+// Resultado de una corrida: los parametros usados y la estacion al horizonte.
+// Al imprimirlo muestra las metricas estandar junto a sus valores teoricos.
+pub struct Report {
+    pub params: Params,
+    pub pool: ServerPool,
+}
+
+impl Report {
+    // rho = lambda / (c * mu)
+    pub fn rho(&self) -> f64 {
+        self.params.arrival_rate / (self.params.servers as f64 * self.params.service_rate)
+    }
+
+    // Segundos observados, del fin del calentamiento al horizonte
+    pub fn observed(&self) -> f64 {
+        (self.params.horizon - self.pool.since()).as_secs_f64()
+    }
+
+    // Fraccion del tiempo observado en que el servidor estuvo ocupado con servicios completados
+    pub fn utilization(&self, server: usize) -> f64 {
+        self.pool.busy_time(server).as_secs_f64() / self.observed()
+    }
+
+    pub fn average_utilization(&self) -> f64 {
+        self.pool.total_busy_time().as_secs_f64() / (self.params.servers as f64 * self.observed())
+    }
+
+    // Probabilidad de que un cliente espere (Erlang C), o None si el sistema es inestable.
+    // Con un servidor es rho.
+    pub fn wait_probability(&self) -> Option<f64> {
+        let rho = self.rho();
+        if rho >= 1.0 {
+            return None;
+        }
+        let offered = self.params.arrival_rate / self.params.service_rate;
+        let mut term = 1.0;
+        let mut below_c = 0.0;
+        for k in 0..self.params.servers {
+            below_c += term;
+            term *= offered / (k + 1) as f64;
+        }
+        let at_c = term / (1.0 - rho);
+        Some(at_c / (below_c + at_c))
+    }
+
+    // Espera promedio teorica en cola, Wq = C / (c * mu - lambda). No considera abandonos.
+    pub fn expected_wait(&self) -> Option<f64> {
+        let capacity = self.params.servers as f64 * self.params.service_rate;
+        self.wait_probability().map(|c| c / (capacity - self.params.arrival_rate))
+    }
+}
+
+// Corrientes del generador de cada entidad. Los ids son fijos: la fuente de llegadas usa
+// siempre la misma y el servidor `index` la siguiente a su indice, asi que cambiar el numero
+// de servidores no altera las llegadas ni los servicios de los servidores que ya estaban.
+const ARRIVALS_STREAM: u64 = 0;
+
+fn server_stream(index: usize) -> u64 {
+    1 + index as u64
+}
+
+// Modelo M/M/c: llegadas de Poisson y `servers` servidores exponenciales identicos
+// que comparten una cola FIFO sin limite; con un servidor es el M/M/1.
+// Ejecuta hasta el horizonte y entrega la estacion para reportarla.
+pub fn run(params: &Params) -> Report {
+    assert!(params.arrival_rate > 0.0 && params.service_rate > 0.0, "las tasas deben ser positivas");
+    assert!(params.warmup < params.horizon, "el calentamiento debe terminar antes del horizonte");
+
+    let mut simulation = Simulation::default();
+    let shared_state = simulation.state();
+
+    let pool_key = with_state!(shared_state, |state| {
+        state.insert(ServerPool::new(params.servers, params.assignment, params.patience))
+    });
+    add_entities(&mut simulation, pool_key, params);
+    simulation.run_with_limit(params.horizon);
+
+    let mut pool = with_state!(shared_state, |state| state.remove(pool_key).unwrap());
+    // Quienes siguen en cola pero ya superaron su paciencia al horizonte tambien abandonaron
+    pool.renege(params.horizon);
+
+    Report { params: *params, pool }
+}
+
+// Agrega y agenda los servidores, la fuente de llegadas y el calentamiento sobre la estacion
+// `station_key`, que ya debe estar en el state.
+fn add_entities<S: Station + 'static>(simulation: &mut Simulation, station_key: StateKey<S>, params: &Params) {
+    let shared_state = simulation.state();
+
+    // Los servidores no necesitan conocer a la fuente, por lo que basta con insertarlos primero
+    // para evitar el truco de insertar un None y reemplazarlo despues.
+    let server_keys: Vec<_> = (0..params.servers)
+        .map(|index| {
+            simulation.add_generator(server(
+                Rc::clone(&shared_state),
+                station_key,
+                index,
+                params.service_rate,
+                Rng::stream(params.seed, server_stream(index)),
+            ))
+        })
+        .collect();
+    let arrivals_key = simulation.add_generator(poisson_arrivals(
+        Rc::clone(&shared_state),
+        station_key,
+        server_keys.clone(),
+        params.arrival_rate,
+        Rng::stream(params.seed, ARRIVALS_STREAM),
+    ));
+
+    for &key in &server_keys {
+        simulation.schedule_now(key);
+    }
+    simulation.schedule_now(arrivals_key);
+    if params.warmup > Duration::ZERO {
+        let warmup_key = simulation.add_generator(warmup(shared_state, station_key, params.warmup));
+        simulation.schedule_now(warmup_key);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params = &self.params;
+        let pool = &self.pool;
+        let waits = pool.wait_times().tally();
+
+        writeln!(
+            f,
+            "[M/M/{}] lambda = {}, mu = {}, horizonte = {:?}, semilla = {}",
+            params.servers, params.arrival_rate, params.service_rate, params.horizon, params.seed
+        )?;
+        if pool.since() > Duration::ZERO {
+            writeln!(f, "  calentamiento descartado:  {:?}", pool.since())?;
+        }
+        writeln!(f, "  clientes atendidos:        {}", waits.count())?;
+        writeln!(f, "  espera promedio en cola:   {:.4} s (desv. est. {:.4} s)", waits.mean(), waits.variance().sqrt())?;
+        if let Some(max) = waits.max() {
+            writeln!(f, "  espera maxima en cola:     {:.4} s", max)?;
+        }
+        match pool.wait_times().confidence_interval(params.confidence) {
+            Some(ci) => writeln!(
+                f,
+                "  IC {} espera en cola:     {:.4} +- {:.4} s ({} lotes, autocorrelacion {:.3})",
+                params.confidence,
+                ci.interval.mean,
+                ci.interval.half_width,
+                pool.wait_times().batch_count(),
+                ci.lag1_correlation
+            )?,
+            None => writeln!(f, "  IC {} espera en cola:     insuficientes observaciones", params.confidence)?,
+        }
+        if pool.servers() > 1 {
+            for server in 0..pool.servers() {
+                writeln!(f, "  utilizacion servidor {}:    {:.4}", server, self.utilization(server))?;
+            }
+        }
+        writeln!(f, "  utilizacion promedio:      {:.4}", self.average_utilization())?;
+        writeln!(f, "  clientes en cola al final: {}", pool.queue_len())?;
+        if let Some(patience) = params.patience {
+            let arrived = waits.count() + pool.reneged() + pool.queue_len() as u64;
+            writeln!(
+                f,
+                "  abandonos (paciencia {:?}): {} ({:.2}% de las llegadas)",
+                patience,
+                pool.reneged(),
+                100.0 * pool.reneged() as f64 / arrived.max(1) as f64
+            )?;
+            writeln!(f, "  (los valores teoricos no consideran abandonos)")?;
+        }
+        match (self.expected_wait(), self.wait_probability()) {
+            (Some(expected_wait), Some(wait_probability)) => writeln!(
+                f,
+                "  teorico: espera promedio {:.4} s, utilizacion {:.4}, P(esperar) {:.4}",
+                expected_wait,
+                self.rho(),
+                wait_probability
+            ),
+            _ => writeln!(f, "  teorico: sistema inestable (rho = {:.4} >= 1), la cola crece sin limite", self.rho()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(arrival_rate: f64, servers: usize) -> Params {
+        Params {
+            arrival_rate,
+            service_rate: 1.0,
+            servers,
+            assignment: Assignment::RoundRobin,
+            horizon: Duration::from_secs(50_000),
+            seed: 42,
+            warmup: Duration::ZERO,
+            confidence: ConfidenceLevel::P95,
+            patience: None,
+        }
+    }
+
+    // Compara una corrida con semilla fija con la teoria: utilizacion cerca de rho y Wq dentro
+    // del intervalo por lotes. Con rho = 0.5 los lotes son casi independientes; con rho mas alto
+    // el intervalo de medias por lotes queda corto mas seguido.
+    fn assert_matches_theory(report: &Report) {
+        assert!(
+            (report.average_utilization() - report.rho()).abs() < 0.02,
+            "utilizacion {}",
+            report.average_utilization()
+        );
+        let expected_wait = report.expected_wait().unwrap();
+        let ci = report.pool.wait_times().confidence_interval(ConfidenceLevel::P95).unwrap().interval;
+        assert!(
+            (ci.mean - expected_wait).abs() <= ci.half_width,
+            "Wq teorico {} fuera de {} +- {}",
+            expected_wait,
+            ci.mean,
+            ci.half_width
+        );
+    }
+
+    // Prueba de integracion de los bloques de colas con el M/M/1
+    #[test]
+    fn single_server_matches_theory() {
+        let report = run(&params(0.5, 1));
+        assert!((report.wait_probability().unwrap() - report.rho()).abs() < 1e-12);
+        assert_matches_theory(&report);
+    }
+
+    #[test]
+    fn two_servers_share_the_load() {
+        let report = run(&params(1.0, 2));
+        assert!(report.utilization(0) > 0.4 && report.utilization(1) > 0.4);
+        assert_matches_theory(&report);
+    }
+
+    // Con calentamiento la utilizacion se mide sobre el tiempo observado y sigue cerca de rho
+    #[test]
+    fn warmup_is_excluded_from_the_report() {
+        let report = run(&Params { warmup: Duration::from_secs(10_000), ..params(0.5, 1) });
+        assert_eq!(report.pool.since(), Duration::from_secs(10_000));
+        assert_eq!(report.observed(), 40_000.0);
+        assert_matches_theory(&report);
+    }
+
+    // Dos servidores conducidos a mano, ocupados 3 s y 1 s dentro de un horizonte de 4 s
+    #[test]
+    fn utilization_over_a_known_window() {
+        let secs = Duration::from_secs;
+        let mut pool = ServerPool::new(2, Assignment::RoundRobin, None);
+        pool.next_customer(0);
+        pool.next_customer(1);
+        assert_eq!(pool.arrive(secs(0)), Some(0));
+        pool.next_customer(0);
+        assert_eq!(pool.arrive(secs(1)), Some(1));
+        pool.next_customer(1);
+        pool.complete(1, secs(2), secs(1));
+        pool.complete(0, secs(3), secs(3));
+
+        let report = Report { params: Params { horizon: secs(4), ..params(1.0, 2) }, pool };
+        assert_eq!(report.utilization(0), 0.75);
+        assert_eq!(report.utilization(1), 0.25);
+        assert_eq!(report.average_utilization(), 0.5);
+    }
+
+    // Estacion que anota los instantes de llegada y delega todo lo demas en un ServerPool
+    struct RecordArrivals {
+        pool: ServerPool,
+        arrivals: Vec<Duration>,
+    }
+
+    impl Station for RecordArrivals {
+        fn arrive(&mut self, now: Duration) -> Option<usize> {
+            self.arrivals.push(now);
+            self.pool.arrive(now)
+        }
+
+        fn next_customer(&mut self, server: usize) -> (Duration, bool) {
+            self.pool.next_customer(server)
+        }
+
+        fn complete(&mut self, server: usize, now: Duration, service: Duration) {
+            self.pool.complete(server, now, service)
+        }
+
+        fn reset_statistics(&mut self, now: Duration) {
+            self.pool.reset_statistics(now)
+        }
+    }
+
+    fn arrivals_with(servers: usize) -> Vec<Duration> {
+        let params = Params { horizon: Duration::from_secs(1_000), ..params(0.5, servers) };
+        let mut simulation = Simulation::default();
+        let shared_state = simulation.state();
+        let station_key = with_state!(shared_state, |state| {
+            state.insert(RecordArrivals { pool: ServerPool::new(servers, params.assignment, None), arrivals: Vec::new() })
+        });
+        add_entities(&mut simulation, station_key, &params);
+        simulation.run_with_limit(params.horizon);
+        with_state!(shared_state, |state| state.remove(station_key).unwrap().arrivals)
+    }
+
+    // Agregar un servidor no debe cambiar la corriente de la fuente de llegadas
+    #[test]
+    fn arrivals_do_not_depend_on_the_number_of_servers() {
+        let single = arrivals_with(1);
+        assert!(single.len() > 100);
+        assert_eq!(single, arrivals_with(2));
+        assert_eq!(single, arrivals_with(3));
+    }
+
+    // Una corrida con semilla de entropia se repite exactamente con la semilla que reporta
+    #[test]
+    fn reported_seed_reproduces_the_run() {
+        let seed = Rng::entropy_seed();
+        let short = |seed| Params { horizon: Duration::from_secs(5_000), seed, ..params(0.5, 2) };
+        let first = run(&short(seed));
+        assert_eq!(first.params.seed, seed);
+        let again = run(&short(first.params.seed));
+        assert_eq!(first.to_string(), again.to_string());
+        assert_eq!(first.pool.wait_times().tally().mean().to_bits(), again.pool.wait_times().tally().mean().to_bits());
+    }
+}
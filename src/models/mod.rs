@@ -0,0 +1,7 @@
+// Modelos de ejemplo mas cercanos a un caso real que el baile de Passivate de main.rs,
+// junto a los bloques reutilizables con los que se construyen.
+pub mod mmc;
+pub mod pool;
+pub mod queue;
+pub mod rng;
+pub mod stats;
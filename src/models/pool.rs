@@ -0,0 +1,270 @@
+use std::{collections::VecDeque, str::FromStr, time::Duration};
+
+use super::{queue::Station, stats::BatchMeans};
+
+// Como elige la estacion entre varios servidores libres cuando llega un cliente
+#[derive(Clone, Copy)]
+pub enum Assignment {
+    // En orden circular a partir del ultimo servidor asignado
+    RoundRobin,
+    // El servidor libre con menos tiempo ocupado acumulado
+    LeastUtilized,
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rr" | "round-robin" => Ok(Assignment::RoundRobin),
+            "least" | "least-utilized" => Ok(Assignment::LeastUtilized),
+            _ => Err(format!("politica de asignacion no soportada: {} (use rr o least)", s)),
+        }
+    }
+}
+
+// State compartido de K servidores identicos con una unica cola FIFO; con K = 1 es la
+// cola de un solo servidor del M/M/1.
+// Un servidor que termina toma directamente al siguiente de la cola, la politica de
+// asignacion solo decide cual de los servidores libres atiende a quien llega.
+pub struct ServerPool {
+    // Reloj del modelo, ver Station
+    clock: Duration,
+    // Instantes de llegada de los clientes que aun esperan ser atendidos
+    waiting: VecDeque<Duration>,
+    // Por servidor: si esta en Passivate esperando clientes
+    idle: Vec<bool>,
+    // Por servidor: tiempo ocupado en servicios completados, contado desde `since`
+    busy_time: Vec<Duration>,
+    // Inicio del periodo observado, el fin del calentamiento o 0 si no hubo
+    since: Duration,
+    assignment: Assignment,
+    // Ultimo servidor asignado, punto de partida de RoundRobin
+    last_assigned: usize,
+    // Tiempo en cola (en segundos) de cada cliente al comenzar su servicio
+    wait_times: BatchMeans,
+    // Espera maxima que tolera un cliente antes de abandonar la cola, None si nunca abandona
+    patience: Option<Duration>,
+    // Clientes que abandonaron la cola sin ser atendidos
+    reneged: u64,
+}
+
+impl ServerPool {
+    pub fn new(servers: usize, assignment: Assignment, patience: Option<Duration>) -> Self {
+        assert!(servers > 0, "la estacion necesita al menos un servidor");
+        Self {
+            clock: Duration::ZERO,
+            waiting: VecDeque::new(),
+            idle: vec![false; servers],
+            busy_time: vec![Duration::ZERO; servers],
+            since: Duration::ZERO,
+            assignment,
+            last_assigned: servers - 1,
+            wait_times: BatchMeans::default(),
+            patience,
+            reneged: 0,
+        }
+    }
+
+    pub fn servers(&self) -> usize {
+        self.idle.len()
+    }
+
+    // Clientes en cola segun la ultima llegada o inicio de servicio. Los que abandonaron
+    // despues de ese instante siguen contados hasta el proximo evento de la estacion.
+    pub fn queue_len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    pub fn busy_time(&self, server: usize) -> Duration {
+        self.busy_time[server]
+    }
+
+    pub fn total_busy_time(&self) -> Duration {
+        self.busy_time.iter().sum()
+    }
+
+    pub fn since(&self) -> Duration {
+        self.since
+    }
+
+    pub fn wait_times(&self) -> &BatchMeans {
+        &self.wait_times
+    }
+
+    pub fn reneged(&self) -> u64 {
+        self.reneged
+    }
+
+    // Retira de la cola a los clientes cuya espera supera la paciencia en el instante `now`.
+    // El abandono se registra recien en la siguiente llegada o cuando un servidor busca al
+    // siguiente cliente, y no en el instante en que ocurre. Como la paciencia es la misma
+    // para todos y la cola es FIFO, basta con revisar el frente, y el orden y la espera de
+    // los atendidos no cambian.
+    pub fn renege(&mut self, now: Duration) {
+        let patience = match self.patience {
+            Some(patience) => patience,
+            None => return,
+        };
+        while let Some(&arrival) = self.waiting.front() {
+            if now - arrival <= patience {
+                break;
+            }
+            self.waiting.pop_front();
+            self.reneged += 1;
+        }
+    }
+}
+
+impl Station for ServerPool {
+    fn arrive(&mut self, now: Duration) -> Option<usize> {
+        self.clock = now;
+        self.renege(now);
+        self.waiting.push_back(now);
+        // El servidor elegido se marca ocupado aqui mismo para que una segunda llegada en el
+        // mismo instante no lo vuelva a activar.
+        let servers = self.idle.len();
+        let chosen = match self.assignment {
+            Assignment::RoundRobin => (1..=servers)
+                .map(|offset| (self.last_assigned + offset) % servers)
+                .find(|&server| self.idle[server]),
+            Assignment::LeastUtilized => (0..servers)
+                .filter(|&server| self.idle[server])
+                .min_by_key(|&server| self.busy_time[server]),
+        }?;
+        self.idle[chosen] = false;
+        self.last_assigned = chosen;
+        Some(chosen)
+    }
+
+    fn next_customer(&mut self, server: usize) -> (Duration, bool) {
+        self.renege(self.clock);
+        match self.waiting.pop_front() {
+            Some(arrival) => {
+                self.wait_times.record((self.clock - arrival).as_secs_f64());
+                (self.clock, true)
+            }
+            None => {
+                self.idle[server] = true;
+                (self.clock, false)
+            }
+        }
+    }
+
+    fn complete(&mut self, server: usize, now: Duration, service: Duration) {
+        self.clock = now;
+        // Un servicio que empezo antes del calentamiento cuenta solo desde su fin
+        self.busy_time[server] += now - (now - service).max(self.since);
+    }
+
+    // Los clientes que siguen en cola conservan su llegada, su espera se registra completa al
+    // ser atendidos. Los abandonos ocurridos antes de `now` se descartan con el resto.
+    fn reset_statistics(&mut self, now: Duration) {
+        self.clock = now;
+        self.renege(now);
+        self.since = now;
+        self.wait_times = BatchMeans::default();
+        self.busy_time.iter_mut().for_each(|busy| *busy = Duration::ZERO);
+        self.reneged = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    // Lleva una estacion de dos servidores por la misma secuencia: ambos parten libres,
+    // llegan clientes en 0 y 1 y cada servidor atiende a uno. El servidor 0 trabaja 3 s y
+    // el 1 trabaja 1 s. Al final los dos quedan libres en t = 3.
+    fn two_servers_after_first_round(assignment: Assignment) -> ServerPool {
+        let mut pool = ServerPool::new(2, assignment, None);
+        assert_eq!(pool.next_customer(0), (secs(0), false));
+        assert_eq!(pool.next_customer(1), (secs(0), false));
+
+        assert_eq!(pool.arrive(secs(0)), Some(0));
+        assert_eq!(pool.next_customer(0), (secs(0), true));
+        assert_eq!(pool.arrive(secs(1)), Some(1));
+        assert_eq!(pool.next_customer(1), (secs(1), true));
+
+        pool.complete(1, secs(2), secs(1));
+        assert_eq!(pool.next_customer(1), (secs(2), false));
+        pool.complete(0, secs(3), secs(3));
+        assert_eq!(pool.next_customer(0), (secs(3), false));
+        pool
+    }
+
+    #[test]
+    fn round_robin_uses_both_servers() {
+        let mut pool = two_servers_after_first_round(Assignment::RoundRobin);
+        assert_eq!(pool.busy_time(0), secs(3));
+        assert_eq!(pool.busy_time(1), secs(1));
+        assert_eq!(pool.total_busy_time(), secs(4));
+        // El ultimo asignado fue el 1, asi que sigue el 0
+        assert_eq!(pool.arrive(secs(4)), Some(0));
+        assert_eq!(pool.wait_times().tally().count(), 2);
+    }
+
+    #[test]
+    fn least_utilized_picks_the_idlest_server() {
+        let mut pool = two_servers_after_first_round(Assignment::LeastUtilized);
+        assert_eq!(pool.arrive(secs(4)), Some(1));
+        // Con el 1 ocupado solo queda el 0
+        assert_eq!(pool.arrive(secs(4)), Some(0));
+        assert_eq!(pool.arrive(secs(4)), None);
+    }
+
+    #[test]
+    fn impatient_customers_renege_with_a_slow_server() {
+        let mut pool = ServerPool::new(1, Assignment::RoundRobin, Some(secs(2)));
+        assert_eq!(pool.next_customer(0), (secs(0), false));
+        assert_eq!(pool.arrive(secs(0)), Some(0));
+        assert_eq!(pool.next_customer(0), (secs(0), true));
+
+        // Con el servidor ocupado los siguientes esperan
+        assert_eq!(pool.arrive(secs(1)), None);
+        assert_eq!(pool.arrive(secs(2)), None);
+        // En t = 4 el que llego en 1 ya espero mas que su paciencia y se purga al llegar otro
+        assert_eq!(pool.arrive(secs(4)), None);
+        assert_eq!(pool.reneged(), 1);
+        assert_eq!(pool.queue_len(), 2);
+
+        // El servicio termina en t = 10 y ninguno de los que esperan sigue en la cola
+        pool.complete(0, secs(10), secs(10));
+        assert_eq!(pool.next_customer(0), (secs(10), false));
+        assert_eq!(pool.reneged(), 3);
+        assert_eq!(pool.queue_len(), 0);
+        assert_eq!(pool.wait_times().tally().count(), 1);
+    }
+
+    #[test]
+    fn reset_statistics_excludes_the_warmup() {
+        let mut pool = ServerPool::new(1, Assignment::RoundRobin, Some(secs(2)));
+        assert_eq!(pool.next_customer(0), (secs(0), false));
+        assert_eq!(pool.arrive(secs(0)), Some(0));
+        assert_eq!(pool.next_customer(0), (secs(0), true));
+        assert_eq!(pool.arrive(secs(1)), None);
+        assert_eq!(pool.arrive(secs(4)), None);
+        assert_eq!(pool.reneged(), 1);
+
+        // El calentamiento termina en t = 5, a mitad del servicio que empezo en 0
+        pool.reset_statistics(secs(5));
+        assert_eq!(pool.since(), secs(5));
+        assert_eq!(pool.reneged(), 0);
+        assert_eq!(pool.wait_times().tally().count(), 0);
+        assert_eq!(pool.queue_len(), 1);
+
+        // Del servicio de 0 a 6 solo cuenta el tramo posterior a t = 5
+        pool.complete(0, secs(6), secs(6));
+        assert_eq!(pool.busy_time(0), secs(1));
+        // El que llego en 4 espero 2 s, su espera completa es del periodo observado
+        assert_eq!(pool.next_customer(0), (secs(6), true));
+        assert_eq!(pool.wait_times().tally().count(), 1);
+        assert_eq!(pool.wait_times().tally().mean(), 2.0);
+        pool.complete(0, secs(9), secs(3));
+        assert_eq!(pool.busy_time(0), secs(4));
+    }
+}
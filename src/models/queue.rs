@@ -0,0 +1,96 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use simulator::{Action, GenBoxed, Key, State, StateKey};
+
+use super::rng::Rng;
+
+// Estacion de servicio con uno o mas servidores, alimentada por `poisson_arrivals`
+// y atendida por un generador `server` por cada servidor.
+//
+// La estacion lleva el reloj del modelo. Un generador no tiene forma de preguntar el tiempo
+// actual, asi que cada entidad lo informa al despertar de un Hold (conoce cuanto espero)
+// y la que sale de Passivate lo lee de la estacion.
+pub trait Station {
+    // Encola un cliente que llega en `now`. Si hay un servidor en Passivate lo marca ocupado
+    // y devuelve su indice para que la llegada lo active.
+    fn arrive(&mut self, now: Duration) -> Option<usize>;
+
+    // El servidor `server` quedo libre y toma al siguiente cliente de la cola, registrando su
+    // espera. Devuelve el instante actual y si habia un cliente; si no, el servidor queda
+    // marcado en Passivate.
+    fn next_customer(&mut self, server: usize) -> (Duration, bool);
+
+    // El servidor `server` termino en `now` un servicio de duracion `service`.
+    fn complete(&mut self, server: usize, now: Duration, service: Duration);
+
+    // Fin del calentamiento en `now`: descarta lo acumulado hasta ahi, y desde ahi las
+    // metricas integradas en el tiempo cuentan solo lo que ocurre despues de `now`.
+    fn reset_statistics(&mut self, now: Duration);
+}
+
+// Fuente de llegadas de Poisson: tiempos entre llegadas exponenciales de tasa `rate`.
+// Cada llegada se encola en la estacion y, si esta elige un servidor en Passivate, lo activa.
+// `server_keys` son las Key de los servidores en el orden de sus indices.
+// Debe agendarse en el instante 0, ya que lleva su propio reloj a partir de ahi.
+pub fn poisson_arrivals<S: Station + 'static>(
+    shared_state: Rc<Cell<State>>,
+    station_key: StateKey<S>,
+    server_keys: Vec<Key>,
+    rate: f64,
+    mut rng: Rng,
+) -> GenBoxed<()> {
+    Box::new(move |_| {
+        let mut now = Duration::ZERO;
+        loop {
+            let interarrival = rng.exponential(rate);
+            yield Action::Hold(interarrival);
+            now += interarrival;
+
+            let wake_server = with_state!(shared_state, |state| state.get_mut(station_key).unwrap().arrive(now));
+            if let Some(server) = wake_server {
+                yield Action::ActivateOne(server_keys[server]);
+            }
+        }
+    })
+}
+
+// Reinicia las estadisticas de la estacion al cumplirse `warmup`, para que reflejen el regimen
+// estacionario y no el arranque con el sistema vacio. Debe agendarse en el instante 0.
+pub fn warmup<S: Station + 'static>(
+    shared_state: Rc<Cell<State>>,
+    station_key: StateKey<S>,
+    warmup: Duration,
+) -> GenBoxed<()> {
+    Box::new(move |_| {
+        yield Action::Hold(warmup);
+        with_state!(shared_state, |state| state.get_mut(station_key).unwrap().reset_statistics(warmup));
+    })
+}
+
+// Servidor `index` de la estacion, con tiempos de servicio exponenciales de tasa `rate`.
+// Atiende a los clientes en orden de llegada, saltando a los que ya abandonaron la cola,
+// y entra en Passivate cuando la cola se vacia.
+pub fn server<S: Station + 'static>(
+    shared_state: Rc<Cell<State>>,
+    station_key: StateKey<S>,
+    index: usize,
+    rate: f64,
+    mut rng: Rng,
+) -> GenBoxed<()> {
+    Box::new(move |_| loop {
+        let (now, found) = with_state!(shared_state, |state| state.get_mut(station_key).unwrap().next_customer(index));
+
+        if !found {
+            // La llegada que nos active ya habra dejado el reloj en su instante
+            yield Action::Passivate;
+            continue;
+        }
+
+        let service = rng.exponential(rate);
+        yield Action::Hold(service);
+
+        with_state!(shared_state, |state| {
+            state.get_mut(station_key).unwrap().complete(index, now + service, service);
+        });
+    })
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Generador pseudoaleatorio minimo (xorshift64*), suficiente para los modelos de ejemplo.
+// No es criptograficamente seguro, solo se busca que una misma semilla reproduzca la corrida.
+pub struct Rng {
+    state: u64,
+    // Numeros uniformes entregados, para comparar el consumo entre corridas
+    draws: u64,
+}
+
+impl Rng {
+    // Corriente 0 de la semilla `seed`
+    pub fn new(seed: u64) -> Self {
+        Self::stream(seed, 0)
+    }
+
+    // Corriente `id` de la semilla `seed`. Cada entidad usa su propia corriente, asi que agregar
+    // o quitar otra entidad no altera los numeros que recibe.
+    // xorshift es lineal, por lo que estados iniciales vecinos entregan secuencias relacionadas;
+    // la semilla y el id pasan por splitmix64 para que semillas o corrientes vecinas no lo sean.
+    pub fn stream(seed: u64, id: u64) -> Self {
+        let state = splitmix64(splitmix64(seed).wrapping_add(id));
+        // xorshift queda atrapado en 0
+        Self { state: if state == 0 { 0x9E37_79B9_7F4A_7C15 } else { state }, draws: 0 }
+    }
+
+    // Semilla distinta en cada llamada, para corridas exploratorias. Hay que registrarla (los
+    // reportes la muestran) para poder repetir despues una corrida interesante.
+    pub fn entropy_seed() -> u64 {
+        // RandomState parte de claves aleatorias del sistema operativo, la hora solo agrega mezcla
+        let mut hasher = RandomState::new().build_hasher();
+        if let Ok(elapsed) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            hasher.write_u128(elapsed.as_nanos());
+        }
+        hasher.finish()
+    }
+
+    // Cantidad de numeros uniformes sacados hasta ahora. Si dos corridas con la misma semilla
+    // divergen, comparar este conteo por entidad muestra cual consumio distinto.
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    // Numero uniforme en el intervalo (0, 1]
+    pub fn uniform(&mut self) -> f64 {
+        self.draws += 1;
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let x = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        ((x >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    // Tiempo exponencial con tasa `rate` (eventos por segundo), por el metodo de la inversa
+    pub fn exponential(&mut self, rate: f64) -> Duration {
+        Duration::from_secs_f64(-self.uniform().ln() / rate)
+    }
+
+    // Elemento de `options` elegido con la misma probabilidad para todos
+    pub fn choose<'a, T>(&mut self, options: &'a [T]) -> &'a T {
+        assert!(!options.is_empty(), "no se puede elegir entre cero opciones");
+        // uniform esta en (0, 1], asi que el techo de u * n va de 1 a n
+        let index = (self.uniform() * options.len() as f64).ceil() as usize - 1;
+        &options[index.min(options.len() - 1)]
+    }
+
+    // Elemento de `options` elegido con probabilidad proporcional a su peso, por ejemplo para
+    // enviar un trabajo a una de varias estaciones. Los pesos no necesitan sumar 1.
+    pub fn choose_weighted<'a, T>(&mut self, options: &'a [(T, f64)]) -> &'a T {
+        assert!(!options.is_empty(), "no se puede elegir entre cero opciones");
+        assert!(
+            options.iter().all(|&(_, weight)| weight.is_finite() && weight >= 0.0),
+            "los pesos deben ser finitos y no negativos"
+        );
+        let total: f64 = options.iter().map(|&(_, weight)| weight).sum();
+        assert!(total > 0.0, "la suma de los pesos debe ser positiva");
+
+        // Como target > 0, una opcion de peso 0 nunca es la primera en alcanzarlo
+        let target = self.uniform() * total;
+        let mut accumulated = 0.0;
+        for (option, weight) in options {
+            accumulated += weight;
+            if target <= accumulated {
+                return option;
+            }
+        }
+        // Por redondeo, la suma acumulada puede quedar apenas por debajo de target
+        options.iter().rev().find(|&&(_, weight)| weight > 0.0).map(|(option, _)| option).unwrap()
+    }
+}
+
+// Funcion de mezcla de splitmix64: biyectiva y con buena avalancha, entradas que difieren
+// en un bit dan salidas sin relacion aparente.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Correlacion de Pearson entre los primeros numeros de dos familias de corrientes
+    fn first_draw_correlation(a: impl Fn(u64) -> Rng, b: impl Fn(u64) -> Rng) -> f64 {
+        let n = 10_000;
+        let pairs: Vec<(f64, f64)> = (0..n).map(|i| (a(i).uniform(), b(i).uniform())).collect();
+        let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n as f64;
+        let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n as f64;
+        let cov: f64 = pairs.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let var_x: f64 = pairs.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let var_y: f64 = pairs.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+        cov / (var_x * var_y).sqrt()
+    }
+
+    #[test]
+    fn neighbouring_seeds_and_streams_are_unrelated() {
+        let seeds = first_draw_correlation(|i| Rng::stream(i, 0), |i| Rng::stream(i + 1, 0));
+        let streams = first_draw_correlation(|i| Rng::stream(42, i), |i| Rng::stream(42, i + 1));
+        assert!(seeds.abs() < 0.05, "semillas vecinas correlacionadas: {}", seeds);
+        assert!(streams.abs() < 0.05, "corrientes vecinas correlacionadas: {}", streams);
+    }
+
+    #[test]
+    fn stream_is_reproducible() {
+        let mut a = Rng::stream(42, 3);
+        let mut b = Rng::stream(42, 3);
+        for _ in 0..100 {
+            assert_eq!(a.uniform().to_bits(), b.uniform().to_bits());
+        }
+    }
+
+    // Cuenta cuantas veces sale cada indice en `draws` elecciones
+    fn frequencies(draws: usize, mut pick: impl FnMut() -> usize, options: usize) -> Vec<f64> {
+        let mut counts = vec![0; options];
+        for _ in 0..draws {
+            counts[pick()] += 1;
+        }
+        counts.into_iter().map(|count| count as f64 / draws as f64).collect()
+    }
+
+    #[test]
+    fn choose_is_uniform() {
+        let mut rng = Rng::new(42);
+        let options = [0, 1, 2, 3];
+        for frequency in frequencies(100_000, || *rng.choose(&options), options.len()) {
+            assert!((frequency - 0.25).abs() < 0.01, "frecuencia {}", frequency);
+        }
+    }
+
+    #[test]
+    fn choose_weighted_matches_the_weights() {
+        let mut rng = Rng::new(42);
+        // Los pesos no suman 1 e incluyen uno nulo, que nunca debe salir
+        let options = [(0, 2.0), (1, 0.0), (2, 5.0), (3, 3.0)];
+        let observed = frequencies(100_000, || *rng.choose_weighted(&options), options.len());
+        for (&(_, weight), frequency) in options.iter().zip(observed) {
+            let expected = weight / 10.0;
+            assert!((frequency - expected).abs() < 0.01, "frecuencia {} para peso {}", frequency, weight);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cero opciones")]
+    fn choose_rejects_empty_slices() {
+        Rng::new(42).choose::<u32>(&[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "suma de los pesos")]
+    fn choose_weighted_rejects_zero_total_weight() {
+        Rng::new(42).choose_weighted(&[("a", 0.0), ("b", 0.0)]);
+    }
+
+    #[test]
+    fn each_helper_draws_once() {
+        let mut rng = Rng::new(42);
+        assert_eq!(rng.draws(), 0);
+        rng.uniform();
+        assert_eq!(rng.draws(), 1);
+        rng.exponential(2.0);
+        assert_eq!(rng.draws(), 2);
+        rng.choose(&[1, 2, 3]);
+        assert_eq!(rng.draws(), 3);
+        rng.choose_weighted(&[('a', 1.0), ('b', 0.0), ('c', 2.0)]);
+        assert_eq!(rng.draws(), 4);
+    }
+
+    #[test]
+    fn entropy_seeds_differ() {
+        assert_ne!(Rng::entropy_seed(), Rng::entropy_seed());
+    }
+}
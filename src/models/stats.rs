@@ -0,0 +1,371 @@
+use std::{fmt, str::FromStr};
+
+// Acumula observaciones y entrega sus estadisticos basicos.
+// Usa el algoritmo de Welford para no perder precision con muchas observaciones.
+#[derive(Default)]
+pub struct Tally {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    max: f64,
+}
+
+impl Tally {
+    pub fn record(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.max = if self.count == 1 { value } else { self.max.max(value) };
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // Varianza muestral, 0 si hay menos de dos observaciones
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    // Mayor observacion, None si no hay ninguna
+    pub fn max(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.max)
+        }
+    }
+
+    // Intervalo de confianza para la media, asumiendo observaciones independientes.
+    // Devuelve None si hay menos de dos observaciones.
+    pub fn confidence_interval(&self, level: ConfidenceLevel, method: IntervalMethod) -> Option<Interval> {
+        if self.count < 2 {
+            return None;
+        }
+        let critical = level.critical_value(method, self.count - 1);
+        let half_width = critical * (self.variance() / self.count as f64).sqrt();
+        Some(Interval { mean: self.mean, half_width })
+    }
+}
+
+// Niveles de confianza soportados
+#[derive(Clone, Copy)]
+pub enum ConfidenceLevel {
+    P90,
+    P95,
+    P99,
+}
+
+// Como se obtiene el valor critico del intervalo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntervalMethod {
+    // Aproximacion normal, solo adecuada con muestras grandes
+    Normal,
+    // t de Student con n - 1 grados de libertad, exacta para observaciones normales
+    StudentT,
+}
+
+impl IntervalMethod {
+    // Metodo por defecto para `count` observaciones. Sobre 1000 observaciones la t y la normal
+    // difieren en menos de un 0.2% en los tres niveles; bajo eso se usa la t, ya que la normal
+    // entrega intervalos demasiado estrechos (un 6.7% al 99% con 30 grados de libertad).
+    pub fn for_sample_size(count: u64) -> Self {
+        if count > 1000 {
+            IntervalMethod::Normal
+        } else {
+            IntervalMethod::StudentT
+        }
+    }
+}
+
+impl ConfidenceLevel {
+    // Valor critico de dos colas del nivel, para `dof` grados de libertad si se usa la t de Student.
+    // Con la t de Student `dof` debe ser al menos 1.
+    pub fn critical_value(self, method: IntervalMethod, dof: u64) -> f64 {
+        // Cuantiles 0.95, 0.975 y 0.995 de la t de Student para 1 a 30 grados de libertad
+        const T_90: [f64; 30] = [
+            6.314, 2.920, 2.353, 2.132, 2.015, 1.943, 1.895, 1.860, 1.833, 1.812, 1.796, 1.782, 1.771, 1.761, 1.753,
+            1.746, 1.740, 1.734, 1.729, 1.725, 1.721, 1.717, 1.714, 1.711, 1.708, 1.706, 1.703, 1.701, 1.699, 1.697,
+        ];
+        const T_95: [f64; 30] = [
+            12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+            2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+        ];
+        const T_99: [f64; 30] = [
+            63.657, 9.925, 5.841, 4.604, 4.032, 3.707, 3.499, 3.355, 3.250, 3.169, 3.106, 3.055, 3.012, 2.977, 2.947,
+            2.921, 2.898, 2.878, 2.861, 2.845, 2.831, 2.819, 2.807, 2.797, 2.787, 2.779, 2.771, 2.763, 2.756, 2.750,
+        ];
+        let (z, table) = match self {
+            ConfidenceLevel::P90 => (1.645, &T_90),
+            ConfidenceLevel::P95 => (1.960, &T_95),
+            ConfidenceLevel::P99 => (2.576, &T_99),
+        };
+        if let IntervalMethod::StudentT = method {
+            assert!(dof >= 1, "la t de Student necesita al menos un grado de libertad");
+        }
+        match method {
+            IntervalMethod::Normal => z,
+            IntervalMethod::StudentT => match table.get(dof as usize - 1) {
+                Some(&t) => t,
+                // Fuera de la tabla se usa la expansion de Cornish-Fisher, con error menor a 0.001
+                None => {
+                    let v = dof as f64;
+                    z + (z.powi(3) + z) / (4.0 * v) + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * v * v)
+                }
+            },
+        }
+    }
+}
+
+impl FromStr for ConfidenceLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0.90" | "0.9" | "90" => Ok(ConfidenceLevel::P90),
+            "0.95" | "95" => Ok(ConfidenceLevel::P95),
+            "0.99" | "99" => Ok(ConfidenceLevel::P99),
+            _ => Err(format!("nivel de confianza no soportado: {} (use 0.90, 0.95 o 0.99)", s)),
+        }
+    }
+}
+
+impl fmt::Display for ConfidenceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfidenceLevel::P90 => write!(f, "90%"),
+            ConfidenceLevel::P95 => write!(f, "95%"),
+            ConfidenceLevel::P99 => write!(f, "99%"),
+        }
+    }
+}
+
+// Intervalo de confianza de la forma `mean +- half_width`
+pub struct Interval {
+    pub mean: f64,
+    pub half_width: f64,
+}
+
+// Numero minimo de lotes por defecto, dentro del rango de 10 a 30 que se suele recomendar
+const DEFAULT_BATCHES: usize = 20;
+
+// Medias por lotes para estimar un intervalo de confianza con una sola corrida larga.
+// Las observaciones de una cola estan autocorrelacionadas (un cliente que espera mucho suele
+// ir seguido de otro que tambien), asi que el intervalo de un Tally seria demasiado estrecho.
+// En cambio se agrupan en lotes consecutivos de igual tamaño cuyas medias son casi independientes.
+//
+// No guarda las observaciones: los lotes empiezan de tamaño 1 y al juntarse 2K lotes completos
+// se fusionan de a pares en K lotes del doble de tamaño. Asi la memoria es O(K) sin importar el
+// largo de la corrida, y siempre hay entre K y 2K - 1 lotes completos.
+pub struct BatchMeans {
+    batches: usize,
+    // Todas las observaciones, incluidas las del lote en curso
+    tally: Tally,
+    // Suma de cada lote completo
+    sums: Vec<f64>,
+    batch_size: u64,
+    // Lote en curso
+    current_sum: f64,
+    current_count: u64,
+}
+
+// Intervalo de confianza construido sobre las medias de los lotes.
+pub struct BatchInterval {
+    pub interval: Interval,
+    // Autocorrelacion de rezago 1 entre medias de lotes consecutivos.
+    // Si no es cercana a 0 los lotes son demasiado cortos y el intervalo no es confiable.
+    pub lag1_correlation: f64,
+}
+
+impl BatchMeans {
+    // `batches` es el numero minimo de lotes sobre el que se construye el intervalo
+    pub fn new(batches: usize) -> Self {
+        assert!(batches >= 2, "se necesitan al menos dos lotes");
+        Self {
+            batches,
+            tally: Tally::default(),
+            sums: Vec::with_capacity(2 * batches),
+            batch_size: 1,
+            current_sum: 0.0,
+            current_count: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: f64) {
+        self.tally.record(value);
+        self.current_sum += value;
+        self.current_count += 1;
+        if self.current_count < self.batch_size {
+            return;
+        }
+        self.sums.push(self.current_sum);
+        self.current_sum = 0.0;
+        self.current_count = 0;
+        if self.sums.len() == 2 * self.batches {
+            for i in 0..self.batches {
+                self.sums[i] = self.sums[2 * i] + self.sums[2 * i + 1];
+            }
+            self.sums.truncate(self.batches);
+            self.batch_size *= 2;
+        }
+    }
+
+    // Estadisticos de todas las observaciones registradas
+    pub fn tally(&self) -> &Tally {
+        &self.tally
+    }
+
+    // Numero de lotes completos, entre K y 2K - 1 una vez que hay al menos K observaciones
+    pub fn batch_count(&self) -> usize {
+        self.sums.len()
+    }
+
+    // Media de cada lote completo. Las observaciones del lote en curso no se incluyen.
+    pub fn batch_means(&self) -> Vec<f64> {
+        self.sums.iter().map(|sum| sum / self.batch_size as f64).collect()
+    }
+
+    // Las medias de los lotes se tratan como observaciones independientes de un Tally.
+    // Se usa siempre la t de Student, ya que suele haber pocos lotes.
+    // Devuelve None mientras haya menos de K lotes completos.
+    pub fn confidence_interval(&self, level: ConfidenceLevel) -> Option<BatchInterval> {
+        if self.sums.len() < self.batches {
+            return None;
+        }
+        let means = self.batch_means();
+        let mut tally = Tally::default();
+        for &m in &means {
+            tally.record(m);
+        }
+        let interval = tally.confidence_interval(level, IntervalMethod::for_sample_size(tally.count()))?;
+        let mean = interval.mean;
+        let squares: f64 = means.iter().map(|m| (m - mean).powi(2)).sum();
+        let lag1_correlation = if squares == 0.0 {
+            0.0
+        } else {
+            means.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum::<f64>() / squares
+        };
+        Some(BatchInterval { interval, lag1_correlation })
+    }
+}
+
+impl Default for BatchMeans {
+    fn default() -> Self {
+        Self::new(DEFAULT_BATCHES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::rng::Rng;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 5e-4, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn normal_critical_values() {
+        assert_close(ConfidenceLevel::P90.critical_value(IntervalMethod::Normal, 0), 1.645);
+        assert_close(ConfidenceLevel::P95.critical_value(IntervalMethod::Normal, 0), 1.960);
+        assert_close(ConfidenceLevel::P99.critical_value(IntervalMethod::Normal, 0), 2.576);
+    }
+
+    #[test]
+    fn student_t_critical_values() {
+        // Tabla
+        assert_close(ConfidenceLevel::P90.critical_value(IntervalMethod::StudentT, 1), 6.314);
+        assert_close(ConfidenceLevel::P95.critical_value(IntervalMethod::StudentT, 10), 2.228);
+        assert_close(ConfidenceLevel::P99.critical_value(IntervalMethod::StudentT, 30), 2.750);
+        // Cornish-Fisher, fuera de la tabla
+        assert_close(ConfidenceLevel::P95.critical_value(IntervalMethod::StudentT, 31), 2.0395);
+        assert_close(ConfidenceLevel::P99.critical_value(IntervalMethod::StudentT, 31), 2.744);
+        assert_close(ConfidenceLevel::P95.critical_value(IntervalMethod::StudentT, 60), 2.000);
+        assert_close(ConfidenceLevel::P90.critical_value(IntervalMethod::StudentT, 120), 1.658);
+    }
+
+    #[test]
+    #[should_panic(expected = "grado de libertad")]
+    fn student_t_needs_one_degree_of_freedom() {
+        ConfidenceLevel::P95.critical_value(IntervalMethod::StudentT, 0);
+    }
+
+    #[test]
+    fn default_method_depends_on_sample_size() {
+        assert_eq!(IntervalMethod::for_sample_size(30), IntervalMethod::StudentT);
+        assert_eq!(IntervalMethod::for_sample_size(1000), IntervalMethod::StudentT);
+        assert_eq!(IntervalMethod::for_sample_size(1001), IntervalMethod::Normal);
+        // En el umbral pasar a la normal achica el valor critico menos de un 0.2%
+        for level in [ConfidenceLevel::P90, ConfidenceLevel::P95, ConfidenceLevel::P99] {
+            let t = level.critical_value(IntervalMethod::StudentT, 1000);
+            let z = level.critical_value(IntervalMethod::Normal, 1000);
+            assert!(t / z - 1.0 < 0.002, "t {} contra z {}", t, z);
+        }
+    }
+
+    #[test]
+    fn tally_interval() {
+        let mut tally = Tally::default();
+        assert!(tally.confidence_interval(ConfidenceLevel::P95, IntervalMethod::StudentT).is_none());
+        for value in 1..=5 {
+            tally.record(value as f64);
+        }
+        // s = sqrt(2.5), t(0.975, 4) = 2.776
+        let ci = tally.confidence_interval(ConfidenceLevel::P95, IntervalMethod::StudentT).unwrap();
+        assert_close(ci.mean, 3.0);
+        assert_close(ci.half_width, 2.776 * (2.5f64 / 5.0).sqrt());
+    }
+
+    #[test]
+    fn tally_max_with_negative_values() {
+        let mut tally = Tally::default();
+        assert_eq!(tally.max(), None);
+        tally.record(-3.0);
+        tally.record(-1.0);
+        assert_eq!(tally.max(), Some(-1.0));
+    }
+
+    #[test]
+    fn batch_means_memory_is_bounded() {
+        let mut batches = BatchMeans::new(10);
+        for i in 0..1_000_000 {
+            batches.record(i as f64);
+        }
+        assert!((10..20).contains(&batches.batch_count()));
+        assert!(batches.sums.capacity() <= 20);
+        assert_eq!(batches.tally().count(), 1_000_000);
+    }
+
+    // Proceso AR(1) con media conocida 0: x_t = 0.5 x_{t-1} + e_t, con e_t uniforme en (-0.5, 0.5].
+    // Sus observaciones estan autocorrelacionadas, pero el intervalo al 95% por lotes
+    // deberia cubrir la media en cerca del 95% de las replicas.
+    #[test]
+    fn batch_means_interval_covers_known_mean() {
+        let replications = 200;
+        let mut covered = 0;
+        for replication in 0..replications {
+            let mut rng = Rng::stream(1, replication);
+            let mut batches = BatchMeans::default();
+            let mut x = 0.0;
+            for _ in 0..20_000 {
+                x = 0.5 * x + rng.uniform() - 0.5;
+                batches.record(x);
+            }
+            let ci = batches.confidence_interval(ConfidenceLevel::P95).unwrap().interval;
+            if ci.mean.abs() <= ci.half_width {
+                covered += 1;
+            }
+        }
+        let coverage = covered as f64 / replications as f64;
+        assert!((0.88..=0.99).contains(&coverage), "cobertura {}", coverage);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{cell::Cell, mem};
+
+use simulator::State;
+
+// Reemplaza la secuencia take/modificar/set sobre el state compartido:
+//
+//     let wake = with_state!(shared_state, |state| {
+//         let queue = state.get_mut(queue_key).unwrap();
+//         queue.idle
+//     });
+//
+// El state se devuelve al terminar el cuerpo, tanto si llega al final como si sale con
+// `return` (que sale solo de la macro, no del generador) o entra en panico.
+// El valor del cuerpo es el valor de la macro.
+//
+// WARNING: no debe usarse alrededor de un yield. El state tiene que estar devuelto antes de
+// ceder el control, por lo que el yield va despues de la macro y no dentro de ella.
+#[macro_export]
+macro_rules! with_state {
+    ($shared_state:expr, |$state:ident| $body:expr) => {
+        $crate::state::with_state(&$shared_state, |$state: &mut ::simulator::State| $body)
+    };
+}
+
+// Devuelve el state al Cell al soltarse, tanto si el cuerpo termina como si sale con `return`
+// o entra en panico.
+struct Restore<'a> {
+    shared_state: &'a Cell<State>,
+    state: State,
+}
+
+impl<'a> Restore<'a> {
+    fn take(shared_state: &'a Cell<State>) -> Self {
+        Self { state: shared_state.take(), shared_state }
+    }
+}
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        self.shared_state.set(mem::take(&mut self.state));
+    }
+}
+
+// Implementacion de `with_state!`, el state se devuelve al soltar `Restore`.
+pub fn with_state<R>(shared_state: &Cell<State>, f: impl FnOnce(&mut State) -> R) -> R {
+    let mut restore = Restore::take(shared_state);
+    f(&mut restore.state)
+}
+
+// Contraparte de solo lectura de `with_state`, para consultas desde el codigo que conduce la
+// simulacion, como leer un contador entre pasos:
+//
+//     let queued = state_ref(&shared_state, |state| state.get(pool_key).unwrap().queue_len());
+//
+// Sigue la misma regla que `with_state!` sobre los yield.
+pub fn state_ref<R>(shared_state: &Cell<State>, f: impl FnOnce(&State) -> R) -> R {
+    let restore = Restore::take(shared_state);
+    f(&restore.state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use simulator::StateKey;
+
+    use super::*;
+
+    // State compartido con un contador, como lo preparan los modelos antes de agregar entidades
+    fn shared_counter(value: u32) -> (Cell<State>, StateKey<u32>) {
+        let mut state = State::default();
+        let key = state.insert(value);
+        (Cell::new(state), key)
+    }
+
+    // Lee el contador directamente del Cell, falla si el state no fue devuelto
+    fn counter(shared_state: &Cell<State>, key: StateKey<u32>) -> u32 {
+        let mut state = shared_state.take();
+        let value = *state.get_mut(key).expect("el state no fue devuelto");
+        shared_state.set(state);
+        value
+    }
+
+    #[test]
+    fn returns_the_body_value() {
+        let (shared_state, key) = shared_counter(1);
+        let value = with_state!(shared_state, |state| {
+            let counter = state.get_mut(key).unwrap();
+            *counter += 1;
+            *counter * 10
+        });
+        assert_eq!(value, 20);
+        assert_eq!(counter(&shared_state, key), 2);
+    }
+
+    #[test]
+    fn early_return_restores_the_state() {
+        let (shared_state, key) = shared_counter(3);
+        let increment = |limit: u32| {
+            with_state!(shared_state, |state| {
+                let counter = state.get_mut(key).unwrap();
+                if *counter >= limit {
+                    return false;
+                }
+                *counter += 1;
+                true
+            })
+        };
+        assert!(!increment(3));
+        assert_eq!(counter(&shared_state, key), 3);
+        assert!(increment(5));
+        assert_eq!(counter(&shared_state, key), 4);
+    }
+
+    #[test]
+    fn panic_restores_the_state() {
+        let (shared_state, key) = shared_counter(7);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            with_state!(shared_state, |state| {
+                *state.get_mut(key).unwrap() += 1;
+                panic!("fallo dentro del cuerpo");
+            })
+        }));
+        assert!(result.is_err());
+        // El cambio hecho antes del panico se conserva junto con el state
+        assert_eq!(counter(&shared_state, key), 8);
+    }
+
+    #[test]
+    fn state_ref_reads_without_disturbing_the_state() {
+        let (shared_state, key) = shared_counter(0);
+        for step in 1..=3 {
+            // Un paso de la simulacion modifica el contador y el codigo que la conduce lo lee
+            with_state!(shared_state, |state| *state.get_mut(key).unwrap() += 1);
+            assert_eq!(state_ref(&shared_state, |state| *state.get(key).unwrap()), step);
+            assert_eq!(counter(&shared_state, key), step);
+        }
+    }
+}